@@ -0,0 +1,15 @@
+use crate::iface::socket_set::SocketHandle;
+
+/// Metadata associated with every socket in a `SocketSet`, tracked by the set itself
+/// rather than by the sockets (which don't know which set, if any, they live in).
+#[derive(Debug, Default)]
+pub struct Meta {
+    /// Handle of this socket within its enclosing `SocketSet`.
+    /// Mainly useful for debugging.
+    pub(crate) handle: SocketHandle,
+    /// Set whenever a [`SocketRef`](crate::iface::socket_set::SocketRef) borrowed
+    /// through `get_mut_tracked` is dropped, and cleared by `clear_dirty`. Lets the
+    /// poll loop find the sockets an application actually touched instead of
+    /// scanning every socket on every tick.
+    pub(crate) dirty: bool,
+}
@@ -1,4 +1,6 @@
 use core::fmt;
+use core::ops::{Deref, DerefMut};
+
 use managed::ManagedSlice;
 
 use super::socket_meta::Meta;
@@ -11,10 +13,16 @@ use crate::socket::{AnySocket, Socket};
 #[derive(Debug, Default)]
 pub struct SocketStorage<'a> {
     inner: Option<Item<'a>>,
+    /// Bumped every time this slot is filled by `add()`, so a handle minted for a
+    /// previous occupant can be told apart from one minted for whatever is here now.
+    generation: u32,
 }
 
 impl<'a> SocketStorage<'a> {
-    pub const EMPTY: Self = Self { inner: None };
+    pub const EMPTY: Self = Self {
+        inner: None,
+        generation: 0,
+    };
 }
 
 /// An item of a socket set.
@@ -22,16 +30,64 @@ impl<'a> SocketStorage<'a> {
 pub(crate) struct Item<'a> {
     pub(crate) meta: Meta,
     pub(crate) socket: Socket<'a>,
+    /// Number of outstanding claims on this socket. A socket added via `add()`
+    /// starts with a single implicit claim, held by the caller of `add()`.
+    pub(crate) refs: usize,
 }
 
 /// A handle, identifying a socket in an Interface.
+///
+/// Besides the slot index, a handle carries the generation the slot was on when the
+/// handle was minted. `SocketSet` bumps a slot's generation every time `add()` fills
+/// it, so a handle to a socket that has since been removed (and the slot reused by an
+/// unrelated socket) is detected as stale rather than silently aliasing the newcomer.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Hash)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
-pub struct SocketHandle(usize);
+pub struct SocketHandle {
+    index: usize,
+    generation: u32,
+}
 
 impl fmt::Display for SocketHandle {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "#{}", self.0)
+        write!(f, "#{}.{}", self.index, self.generation)
+    }
+}
+
+/// A smart pointer to a socket, obtained from [`SocketSet::get_mut_tracked`].
+///
+/// Dropping a `SocketRef` marks the underlying socket's slot as dirty, so that an
+/// interface driving event-based polling can tell, via
+/// [`SocketSet::iter_dirty_mut`], which sockets an application actually mutated
+/// without scanning the whole set.
+pub struct SocketRef<'a, T: 'a> {
+    socket: &'a mut T,
+    meta: &'a mut Meta,
+}
+
+impl<'a, T> SocketRef<'a, T> {
+    fn new(socket: &'a mut T, meta: &'a mut Meta) -> Self {
+        SocketRef { socket, meta }
+    }
+}
+
+impl<'a, T> Deref for SocketRef<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.socket
+    }
+}
+
+impl<'a, T> DerefMut for SocketRef<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.socket
+    }
+}
+
+impl<'a, T> Drop for SocketRef<'a, T> {
+    fn drop(&mut self) {
+        self.meta.dirty = true;
     }
 }
 
@@ -62,11 +118,17 @@ impl<'a> SocketSet<'a> {
     pub fn add<T: AnySocket<'a>>(&mut self, socket: T) -> SocketHandle {
         fn put<'a>(index: usize, slot: &mut SocketStorage<'a>, socket: Socket<'a>) -> SocketHandle {
             net_trace!("[{}]: adding", index);
-            let handle = SocketHandle(index);
+            let generation = slot.generation.wrapping_add(1);
+            let handle = SocketHandle { index, generation };
             let mut meta = Meta::default();
             meta.handle = handle;
             *slot = SocketStorage {
-                inner: Some(Item { meta, socket }),
+                inner: Some(Item {
+                    meta,
+                    socket,
+                    refs: 1,
+                }),
+                generation,
             };
             handle
         }
@@ -83,20 +145,54 @@ impl<'a> SocketSet<'a> {
             ManagedSlice::Borrowed(_) => panic!("adding a socket to a full SocketSet"),
             #[cfg(feature = "alloc")]
             ManagedSlice::Owned(sockets) => {
-                sockets.push(SocketStorage { inner: None });
+                sockets.push(SocketStorage::EMPTY);
                 let index = sockets.len() - 1;
                 put(index, &mut sockets[index], socket)
             }
         }
     }
 
+    /// Look up the slot for `handle`, checking that it's in bounds and that the
+    /// slot's generation still matches the one the handle was minted with.
+    fn slot(&self, handle: SocketHandle) -> Result<&SocketStorage<'a>, SocketSetError> {
+        let slot = self
+            .sockets
+            .get(handle.index)
+            .ok_or(SocketSetError::OutOfBounds)?;
+
+        if slot.generation != handle.generation {
+            return Err(SocketSetError::Stale);
+        }
+
+        Ok(slot)
+    }
+
+    /// Mutable counterpart of [`slot`](Self::slot).
+    fn slot_mut(&mut self, handle: SocketHandle) -> Result<&mut SocketStorage<'a>, SocketSetError> {
+        let slot = self
+            .sockets
+            .get_mut(handle.index)
+            .ok_or(SocketSetError::OutOfBounds)?;
+
+        if slot.generation != handle.generation {
+            return Err(SocketSetError::Stale);
+        }
+
+        Ok(slot)
+    }
+
     /// Get a socket from the set by its handle, as mutable.
     ///
     /// # Panics
     /// This function may panic if the handle does not belong to this socket set
     /// or the socket has the wrong type.
     pub fn get<T: AnySocket<'a>>(&self, handle: SocketHandle) -> &T {
-        match self.sockets[handle.0].inner.as_ref() {
+        let slot = match self.slot(handle) {
+            Ok(slot) => slot,
+            Err(SocketSetError::Stale) => panic!("handle refers to a stale generation"),
+            Err(_) => panic!("handle does not refer to a valid socket"),
+        };
+        match slot.inner.as_ref() {
             Some(item) => {
                 T::downcast(&item.socket).expect("handle refers to a socket of a wrong type")
             }
@@ -110,20 +206,56 @@ impl<'a> SocketSet<'a> {
     /// This function may panic if the handle does not belong to this socket set
     /// or the socket has the wrong type.
     pub fn get_mut<T: AnySocket<'a>>(&mut self, handle: SocketHandle) -> &mut T {
-        match self.sockets[handle.0].inner.as_mut() {
+        let slot = match self.slot_mut(handle) {
+            Ok(slot) => slot,
+            Err(SocketSetError::Stale) => panic!("handle refers to a stale generation"),
+            Err(_) => panic!("handle does not refer to a valid socket"),
+        };
+        match slot.inner.as_mut() {
             Some(item) => T::downcast_mut(&mut item.socket)
                 .expect("handle refers to a socket of a wrong type"),
             None => panic!("handle does not refer to a valid socket"),
         }
     }
 
+    /// Get a mutable socket from the set by its handle, wrapped in a [`SocketRef`]
+    /// that marks the socket dirty when the reference is dropped.
+    ///
+    /// Use this instead of [`get_mut`](Self::get_mut) when driving event-based
+    /// polling: after servicing the set, [`iter_dirty_mut`](Self::iter_dirty_mut)
+    /// yields only the sockets an application actually touched.
+    ///
+    /// # Panics
+    /// This function may panic if the handle does not belong to this socket set
+    /// or the socket has the wrong type.
+    pub fn get_mut_tracked<T: AnySocket<'a>>(&mut self, handle: SocketHandle) -> SocketRef<'_, T> {
+        let slot = match self.slot_mut(handle) {
+            Ok(slot) => slot,
+            Err(SocketSetError::Stale) => panic!("handle refers to a stale generation"),
+            Err(_) => panic!("handle does not refer to a valid socket"),
+        };
+        match &mut slot.inner {
+            Some(item) => {
+                let socket = T::downcast_mut(&mut item.socket)
+                    .expect("handle refers to a socket of a wrong type");
+                SocketRef::new(socket, &mut item.meta)
+            }
+            None => panic!("handle does not refer to a valid socket"),
+        }
+    }
+
     /// Remove a socket from the set, without changing its state.
     ///
     /// # Panics
     /// This function may panic if the handle does not belong to this socket set.
     pub fn remove(&mut self, handle: SocketHandle) -> Socket<'a> {
-        net_trace!("[{}]: removing", handle.0);
-        match self.sockets[handle.0].inner.take() {
+        net_trace!("[{}]: removing", handle.index);
+        let slot = match self.slot_mut(handle) {
+            Ok(slot) => slot,
+            Err(SocketSetError::Stale) => panic!("handle refers to a stale generation"),
+            Err(_) => panic!("handle does not refer to a valid socket"),
+        };
+        match slot.inner.take() {
             Some(item) => item.socket,
             None => panic!("handle does not refer to a valid socket"),
         }
@@ -148,6 +280,118 @@ impl<'a> SocketSet<'a> {
     pub(crate) fn items_mut(&mut self) -> impl Iterator<Item = &mut Item<'a>> + '_ {
         self.sockets.iter_mut().filter_map(|x| x.inner.as_mut())
     }
+
+    /// Get a mutable iterator over only the sockets marked dirty by a
+    /// [`get_mut_tracked`](Self::get_mut_tracked) borrow since the last
+    /// [`clear_dirty`](Self::clear_dirty).
+    pub fn iter_dirty_mut(&mut self) -> impl Iterator<Item = (SocketHandle, &mut Socket<'a>)> {
+        self.items_mut()
+            .filter(|i| i.meta.dirty)
+            .map(|i| (i.meta.handle, &mut i.socket))
+    }
+
+    /// Clear the dirty flag on every socket in the set.
+    ///
+    /// Call this once you've finished draining [`iter_dirty_mut`](Self::iter_dirty_mut)
+    /// so the next round only reports sockets touched since this call.
+    pub fn clear_dirty(&mut self) {
+        for item in self.items_mut() {
+            item.meta.dirty = false;
+        }
+    }
+
+    /// Add a claim on the socket identified by `handle`, keeping it alive until a
+    /// matching number of [`release`](Self::release) calls have been made.
+    pub fn retain(&mut self, handle: SocketHandle) -> Result<(), SocketSetError> {
+        let item = self
+            .slot_mut(handle)?
+            .inner
+            .as_mut()
+            .ok_or(SocketSetError::Vacant)?;
+
+        item.refs += 1;
+        Ok(())
+    }
+
+    /// Release a claim on the socket identified by `handle`. Once the reference count
+    /// reaches zero, the socket is removed from the set and dropped.
+    pub fn release(&mut self, handle: SocketHandle) -> Result<(), SocketSetError> {
+        let slot = self.slot_mut(handle)?;
+
+        let item = slot.inner.as_mut().ok_or(SocketSetError::Vacant)?;
+        item.refs = item.refs.saturating_sub(1);
+
+        if item.refs == 0 {
+            net_trace!("[{}]: refcount reached zero, removing", handle.index);
+            slot.inner.take();
+        }
+
+        Ok(())
+    }
+
+    /// Remove every socket in the set whose reference count has reached zero.
+    ///
+    /// Normally not needed, since [`release`](Self::release) already removes a
+    /// socket as soon as its count hits zero; provided as a safety net.
+    pub fn prune(&mut self) {
+        for slot in self.sockets.iter_mut() {
+            if matches!(&slot.inner, Some(item) if item.refs == 0) {
+                slot.inner.take();
+            }
+        }
+    }
+
+    /// Return the current reference count of the socket identified by `handle`.
+    pub fn ref_count(&self, handle: SocketHandle) -> Result<usize, SocketSetError> {
+        let item = self
+            .slot(handle)?
+            .inner
+            .as_ref()
+            .ok_or(SocketSetError::Vacant)?;
+
+        Ok(item.refs)
+    }
+
+    /// Borrow `N` distinct sockets mutably at once.
+    ///
+    /// Returns `SocketSetError::Duplicate` if any two handles in `handles` name the
+    /// same slot.
+    pub fn get_disjoint_mut<const N: usize>(
+        &mut self,
+        handles: [SocketHandle; N],
+    ) -> Result<[&mut Socket<'a>; N], SocketSetError> {
+        for i in 0..N {
+            for j in (i + 1)..N {
+                if handles[i].index == handles[j].index {
+                    return Err(SocketSetError::Duplicate);
+                }
+            }
+        }
+
+        let mut indices = [0usize; N];
+        for (i, handle) in handles.iter().enumerate() {
+            let slot = self.slot(*handle)?;
+            if slot.inner.is_none() {
+                return Err(SocketSetError::Vacant);
+            }
+            indices[i] = handle.index;
+        }
+
+        let slice: &mut [SocketStorage<'a>] = &mut self.sockets;
+        let base = slice.as_mut_ptr();
+
+        // SAFETY: every index in `indices` was just checked to be in bounds (via
+        // `self.slot`) and distinct from every other index (via the `Duplicate`
+        // check above), so the `N` pointers below never alias the same slot.
+        Ok(core::array::from_fn(|i| {
+            let storage = unsafe { &mut *base.add(indices[i]) };
+            &mut storage
+                .inner
+                .as_mut()
+                .expect("slot was checked to be occupied above")
+                .socket
+        }))
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -158,6 +402,10 @@ pub enum SocketSetError {
     Vacant,
     /// 要求した T と実体の型が異なる
     WrongType,
+    /// ハンドルの世代がスロットの世代と一致しない（スロットは再利用済み）
+    Stale,
+    /// 複数ハンドルの要求に同じスロットが重複して含まれている
+    Duplicate,
 }
 
 impl core::fmt::Display for SocketSetError {
@@ -166,6 +414,8 @@ impl core::fmt::Display for SocketSetError {
             SocketSetError::OutOfBounds => write!(f, "handle is out of bounds"),
             SocketSetError::Vacant => write!(f, "handle does not refer to a valid socket"),
             SocketSetError::WrongType => write!(f, "handle refers to a socket of a wrong type"),
+            SocketSetError::Stale => write!(f, "handle refers to a stale generation"),
+            SocketSetError::Duplicate => write!(f, "handles refer to overlapping slots"),
         }
     }
 }
@@ -176,10 +426,7 @@ impl std::error::Error for SocketSetError {}
 impl<'a> SocketSet<'a> {
     /// パニックしない版: 参照取得
     pub fn try_get<T: AnySocket<'a>>(&self, handle: SocketHandle) -> Result<&T, SocketSetError> {
-        let entry = self
-            .sockets
-            .get(handle.0)
-            .ok_or(SocketSetError::OutOfBounds)?;
+        let entry = self.slot(handle)?;
 
         let item = entry.inner.as_ref().ok_or(SocketSetError::Vacant)?;
 
@@ -191,10 +438,7 @@ impl<'a> SocketSet<'a> {
         &mut self,
         handle: SocketHandle,
     ) -> Result<&mut T, SocketSetError> {
-        let entry = self
-            .sockets
-            .get_mut(handle.0)
-            .ok_or(SocketSetError::OutOfBounds)?;
+        let entry = self.slot_mut(handle)?;
 
         let item = entry.inner.as_mut().ok_or(SocketSetError::Vacant)?;
 
@@ -203,14 +447,145 @@ impl<'a> SocketSet<'a> {
 
     /// パニックしない版: 削除（状態は維持したまま取り出す）
     pub fn try_remove(&mut self, handle: SocketHandle) -> Result<Socket<'a>, SocketSetError> {
-        net_trace!("[{}]: removing", handle.0);
+        net_trace!("[{}]: removing", handle.index);
 
-        let entry = self
-            .sockets
-            .get_mut(handle.0)
-            .ok_or(SocketSetError::OutOfBounds)?;
+        let entry = self.slot_mut(handle)?;
 
         let item = entry.inner.take().ok_or(SocketSetError::Vacant)?;
         Ok(item.socket)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use std::vec;
+
+    use super::*;
+    use crate::socket::tcp;
+
+    fn tcp_socket() -> tcp::Socket<'static> {
+        tcp::Socket::new(
+            tcp::SocketBuffer::new(vec![0; 64]),
+            tcp::SocketBuffer::new(vec![0; 64]),
+        )
+    }
+
+    #[test]
+    fn stale_handle_after_slot_reuse() {
+        let mut set = SocketSet::new(vec![]);
+
+        let h1 = set.add(tcp_socket());
+        set.remove(h1);
+        let h2 = set.add(tcp_socket());
+
+        // Slot reused, generation bumped: the old handle must not alias the new one.
+        assert_eq!(h1.index, h2.index);
+        assert_ne!(h1.generation, h2.generation);
+        assert_eq!(
+            set.try_get::<tcp::Socket>(h1).unwrap_err(),
+            SocketSetError::Stale
+        );
+        assert!(set.try_get::<tcp::Socket>(h2).is_ok());
+    }
+
+    #[test]
+    fn refcount_lifecycle() {
+        let mut set = SocketSet::new(vec![]);
+
+        let h = set.add(tcp_socket());
+        assert_eq!(set.ref_count(h), Ok(1));
+
+        set.retain(h).unwrap();
+        assert_eq!(set.ref_count(h), Ok(2));
+
+        set.release(h).unwrap();
+        assert_eq!(set.ref_count(h), Ok(1));
+        assert!(set.try_get::<tcp::Socket>(h).is_ok());
+
+        set.release(h).unwrap();
+        // refs hit zero: `release` has already removed the socket.
+        assert_eq!(set.ref_count(h), Err(SocketSetError::Vacant));
+        assert_eq!(
+            set.try_get::<tcp::Socket>(h).unwrap_err(),
+            SocketSetError::Vacant
+        );
+    }
+
+    #[test]
+    fn ref_count_reports_vacant_and_stale() {
+        let mut set = SocketSet::new(vec![]);
+        let h1 = set.add(tcp_socket());
+        set.remove(h1);
+
+        // Slot is empty but its generation still matches `h1`.
+        assert_eq!(set.ref_count(h1), Err(SocketSetError::Vacant));
+        assert_eq!(set.retain(h1), Err(SocketSetError::Vacant));
+
+        // Refilling the slot bumps its generation, so `h1` is now stale.
+        set.add(tcp_socket());
+        assert_eq!(set.ref_count(h1), Err(SocketSetError::Stale));
+    }
+
+    #[test]
+    fn prune_sweeps_slots_with_zero_refs() {
+        let mut set = SocketSet::new(vec![]);
+
+        let h = set.add(tcp_socket());
+        // Bypass `release`'s own removal to exercise `prune`'s independent sweep.
+        set.slot_mut(h).unwrap().inner.as_mut().unwrap().refs = 0;
+
+        set.prune();
+
+        assert_eq!(set.ref_count(h), Err(SocketSetError::Vacant));
+    }
+
+    #[test]
+    fn get_disjoint_mut_rejects_duplicates_and_borrows_distinct_sockets() {
+        let mut set = SocketSet::new(vec![]);
+
+        let a = set.add(tcp_socket());
+        let b = set.add(tcp_socket());
+        let c = set.add(tcp_socket());
+
+        assert_eq!(
+            set.get_disjoint_mut([a, a]).unwrap_err(),
+            SocketSetError::Duplicate
+        );
+
+        let [sa, sb, sc] = set.get_disjoint_mut([a, b, c]).unwrap();
+        assert!(!core::ptr::eq(sa, sb));
+        assert!(!core::ptr::eq(sb, sc));
+    }
+
+    #[test]
+    fn get_mut_tracked_marks_dirty_on_drop() {
+        let mut set = SocketSet::new(vec![]);
+
+        let touched = set.add(tcp_socket());
+        let untouched = set.add(tcp_socket());
+
+        assert_eq!(set.iter_dirty_mut().count(), 0);
+
+        {
+            let _socket = set.get_mut_tracked::<tcp::Socket>(touched);
+            // Dropped at the end of this block, marking `touched`'s slot dirty.
+        }
+
+        let dirty: std::vec::Vec<_> = set.iter_dirty_mut().map(|(h, _)| h).collect();
+        assert_eq!(dirty, [touched]);
+        assert_ne!(touched, untouched);
+    }
+
+    #[test]
+    fn clear_dirty_resets_for_next_round() {
+        let mut set = SocketSet::new(vec![]);
+        let h = set.add(tcp_socket());
+
+        let _ = set.get_mut_tracked::<tcp::Socket>(h);
+        assert_eq!(set.iter_dirty_mut().count(), 1);
+
+        set.clear_dirty();
+        assert_eq!(set.iter_dirty_mut().count(), 0);
+    }
+}
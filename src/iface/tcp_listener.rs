@@ -0,0 +1,104 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use crate::iface::socket_set::{SocketHandle, SocketSet};
+use crate::socket::tcp;
+use crate::wire::IpListenEndpoint;
+
+/// A backlog of [`tcp::Socket`]s kept in a [`SocketSet`], bound to one local
+/// endpoint, that hands out the handle of whichever backlog socket becomes a live
+/// connection, re-arming a replacement listener in its place.
+#[cfg(feature = "alloc")]
+pub struct TcpListener<'a> {
+    endpoint: IpListenEndpoint,
+    make_socket: Box<dyn FnMut() -> tcp::Socket<'a>>,
+    backlog: Vec<SocketHandle>,
+}
+
+#[cfg(feature = "alloc")]
+impl<'a> TcpListener<'a> {
+    /// Create a listener with `backlog` sockets, each built by calling `make_socket`
+    /// and put into `Listen` state on `endpoint`.
+    pub fn new<F>(
+        sockets: &mut SocketSet<'a>,
+        endpoint: IpListenEndpoint,
+        backlog: usize,
+        mut make_socket: F,
+    ) -> Result<Self, tcp::ListenError>
+    where
+        F: FnMut() -> tcp::Socket<'a> + 'static,
+    {
+        let mut handles = Vec::with_capacity(backlog);
+        for _ in 0..backlog {
+            let mut socket = make_socket();
+            socket.listen(endpoint)?;
+            handles.push(sockets.add(socket));
+        }
+
+        Ok(TcpListener {
+            endpoint,
+            make_socket: Box::new(make_socket),
+            backlog: handles,
+        })
+    }
+
+    /// Poll the backlog for a socket that has moved out of `Listen` into an active
+    /// connection, re-arming a fresh listening socket in its place.
+    ///
+    /// The returned handle is now owned by the caller: once the connection is done
+    /// with, it's the caller's responsibility to remove it from `sockets`.
+    pub fn poll_accept(&mut self, sockets: &mut SocketSet<'a>) -> Option<SocketHandle> {
+        let slot = self.backlog.iter_mut().find(|handle| {
+            let socket = sockets.get::<tcp::Socket>(**handle);
+            // `is_active()` is false in `Closed`, `TimeWait` and `Listen`; the extra
+            // `!is_listening()` makes explicit that a socket still waiting for a peer
+            // is never handed out.
+            socket.is_active() && !socket.is_listening()
+        })?;
+
+        let accepted = *slot;
+
+        let mut fresh = (self.make_socket)();
+        // Best-effort: if `listen` fails here the replacement stays `Closed` in its
+        // backlog slot and is never handed out; `close_all` still removes it.
+        let _ = fresh.listen(self.endpoint);
+        *slot = sockets.add(fresh);
+
+        Some(accepted)
+    }
+
+    /// Remove every backlog socket (listening or not) from `sockets`.
+    ///
+    /// Handles already handed out by `poll_accept` are not affected.
+    pub fn close_all(&mut self, sockets: &mut SocketSet<'a>) {
+        for handle in self.backlog.drain(..) {
+            sockets.remove(handle);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use std::vec;
+
+    use super::*;
+
+    fn tcp_socket() -> tcp::Socket<'static> {
+        tcp::Socket::new(
+            tcp::SocketBuffer::new(vec![0; 64]),
+            tcp::SocketBuffer::new(vec![0; 64]),
+        )
+    }
+
+    #[test]
+    fn fresh_listener_does_not_yield_until_connected() {
+        let mut sockets = SocketSet::new(vec![]);
+        let mut listener =
+            TcpListener::new(&mut sockets, IpListenEndpoint::from(1234), 2, tcp_socket).unwrap();
+
+        // Every backlog socket is only `Listen`ing: nothing has connected yet, so
+        // `poll_accept` must not mistake a listener for an accepted connection.
+        assert!(listener.poll_accept(&mut sockets).is_none());
+    }
+}
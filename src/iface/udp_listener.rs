@@ -0,0 +1,103 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use crate::iface::socket_set::{SocketHandle, SocketSet};
+use crate::socket::udp;
+use crate::wire::IpListenEndpoint;
+
+/// A backlog of [`udp::Socket`]s kept in a [`SocketSet`], bound to one local
+/// endpoint, that hands out the handle of whichever backlog socket has a datagram
+/// waiting, re-arming a replacement listener in its place.
+///
+/// UDP has no connection handshake, so unlike [`TcpListener`](super::tcp_listener::TcpListener)
+/// a backlog socket is handed off as soon as it has something to `recv`, not when
+/// it reaches some distinct "connected" state.
+#[cfg(feature = "alloc")]
+pub struct UdpListener<'a> {
+    endpoint: IpListenEndpoint,
+    make_socket: Box<dyn FnMut() -> udp::Socket<'a>>,
+    backlog: Vec<SocketHandle>,
+}
+
+#[cfg(feature = "alloc")]
+impl<'a> UdpListener<'a> {
+    /// Create a listener with `backlog` sockets, each built by calling `make_socket`
+    /// and bound to `endpoint`.
+    pub fn new<F>(
+        sockets: &mut SocketSet<'a>,
+        endpoint: IpListenEndpoint,
+        backlog: usize,
+        mut make_socket: F,
+    ) -> Result<Self, udp::BindError>
+    where
+        F: FnMut() -> udp::Socket<'a> + 'static,
+    {
+        let mut handles = Vec::with_capacity(backlog);
+        for _ in 0..backlog {
+            let mut socket = make_socket();
+            socket.bind(endpoint)?;
+            handles.push(sockets.add(socket));
+        }
+
+        Ok(UdpListener {
+            endpoint,
+            make_socket: Box::new(make_socket),
+            backlog: handles,
+        })
+    }
+
+    /// Poll the backlog for a socket with a datagram waiting, re-arming a fresh
+    /// bound socket in its place.
+    ///
+    /// The returned handle is now owned by the caller: once it's done with the
+    /// datagram(s), it's the caller's responsibility to remove it from `sockets`.
+    pub fn poll_accept(&mut self, sockets: &mut SocketSet<'a>) -> Option<SocketHandle> {
+        let slot = self
+            .backlog
+            .iter_mut()
+            .find(|handle| sockets.get::<udp::Socket>(**handle).can_recv())?;
+
+        let accepted = *slot;
+
+        let mut fresh = (self.make_socket)();
+        // Best-effort: if `bind` fails here the replacement stays unbound in its
+        // backlog slot and is never handed out; `close_all` still removes it.
+        let _ = fresh.bind(self.endpoint);
+        *slot = sockets.add(fresh);
+
+        Some(accepted)
+    }
+
+    /// Remove every backlog socket from `sockets`.
+    ///
+    /// Handles already handed out by `poll_accept` are not affected.
+    pub fn close_all(&mut self, sockets: &mut SocketSet<'a>) {
+        for handle in self.backlog.drain(..) {
+            sockets.remove(handle);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use std::vec;
+
+    use super::*;
+
+    fn udp_socket() -> udp::Socket<'static> {
+        udp::Socket::new(
+            udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 4], vec![0; 256]),
+            udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 4], vec![0; 256]),
+        )
+    }
+
+    #[test]
+    fn fresh_listener_does_not_yield_until_a_datagram_arrives() {
+        let mut sockets = SocketSet::new(vec![]);
+        let mut listener =
+            UdpListener::new(&mut sockets, IpListenEndpoint::from(1234), 2, udp_socket).unwrap();
+
+        assert!(listener.poll_accept(&mut sockets).is_none());
+    }
+}